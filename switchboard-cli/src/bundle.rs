@@ -0,0 +1,136 @@
+//! Withdrawal-bundle inspection and ACK vote management.
+//!
+//! BIP300/301 withdrawal bundles are mainchain objects: every few blocks
+//! the miners on the mainchain combine each sidechain's pending
+//! withdrawals into a single bundle, prioritized by fee, and accumulate
+//! M4 ACK votes for it in the coinbase until it either succeeds or its
+//! age-out window expires. This module queries the mainchain RPC for that
+//! state and drives the upvote/downvote commands that an operator running
+//! switchboard uses to support or abandon the active bundle.
+
+use crate::Sidechain;
+use anyhow::Result;
+use serde::Deserialize;
+use ureq_jsonrpc::{json, Client};
+
+/// A single withdrawal's position within a sidechain's proposed bundle, as
+/// returned by `listwithdrawals`.
+#[derive(Debug, Deserialize)]
+pub struct WithdrawalEntry {
+    pub txid: bitcoin::Txid,
+    pub priority: usize,
+    #[serde(rename = "amountsat")]
+    pub amount_sat: u64,
+    #[serde(rename = "feesat")]
+    pub fee_sat: u64,
+}
+
+/// One entry of `listwithdrawalstatus`'s response. The mainchain RPC
+/// returns an array of status entries (not a single object), one per
+/// bundle it still has a status for; in practice a sidechain has at most
+/// one bundle actively accumulating ACKs at a time, so callers take the
+/// first entry as "the" active bundle.
+#[derive(Debug, Deserialize)]
+struct BundleStatus {
+    #[serde(rename = "bundlehash")]
+    hash: String,
+    #[serde(rename = "workscore")]
+    ack_count: u16,
+    #[serde(rename = "blocksleft")]
+    blocks_left: u16,
+}
+
+/// The bundle currently proposed for a sidechain, if any, together with
+/// the individual withdrawals it bundles.
+#[derive(Debug)]
+pub struct Bundle {
+    pub hash: String,
+    pub ack_count: u16,
+    pub blocks_left: u16,
+    pub withdrawals: Vec<WithdrawalEntry>,
+}
+
+fn get_bundle(main: &Client, sidechain: Sidechain) -> Result<Option<Bundle>> {
+    let statuses = main
+        .send_request::<Vec<BundleStatus>>("listwithdrawalstatus", &[json!(sidechain.number())])?;
+    let Some(status) = statuses.into_iter().next() else {
+        return Ok(None);
+    };
+    let withdrawals =
+        main.send_request::<Vec<WithdrawalEntry>>("listwithdrawals", &[json!(sidechain.number())])?;
+    Ok(Some(Bundle {
+        hash: status.hash,
+        ack_count: status.ack_count,
+        blocks_left: status.blocks_left,
+        withdrawals,
+    }))
+}
+
+/// Print the `bundle status` summary table across all sidechains, in the
+/// same style as `Getbalances`/`Getblockcounts`.
+pub fn status(main: &Client) -> Result<()> {
+    println!(
+        "{:<10}{:>12}{:>10}{:>14}",
+        "sidechain", "bundle", "acks", "blocks left"
+    );
+    for sidechain in [Sidechain::Zcash, Sidechain::Ethereum] {
+        match get_bundle(main, sidechain)? {
+            Some(bundle) => println!(
+                "{:<10}{:>12}{:>10}{:>14}",
+                sidechain.to_string(),
+                &bundle.hash[..8.min(bundle.hash.len())],
+                bundle.ack_count,
+                bundle.blocks_left
+            ),
+            None => println!("{:<10}{:>12}", sidechain.to_string(), "none"),
+        }
+    }
+    Ok(())
+}
+
+/// Show the full proposed bundle for a single sidechain: every
+/// withdrawal's position/priority and the bundle's overall vote count and
+/// remaining lifetime.
+pub fn show(main: &Client, sidechain: Sidechain) -> Result<()> {
+    match get_bundle(main, sidechain)? {
+        None => println!("{} has no proposed bundle", sidechain),
+        Some(bundle) => {
+            println!(
+                "bundle {} for {} ({} acks, {} blocks left)",
+                bundle.hash, sidechain, bundle.ack_count, bundle.blocks_left
+            );
+            println!(
+                "{:<6}{:<70}{:>16}{:>12}",
+                "#", "txid", "amount (sat)", "fee (sat)"
+            );
+            for withdrawal in &bundle.withdrawals {
+                println!(
+                    "{:<6}{:<70}{:>16}{:>12}",
+                    withdrawal.priority, withdrawal.txid, withdrawal.amount_sat, withdrawal.fee_sat
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Cast an M4 ACK vote in favor of the active bundle for `sidechain`.
+pub fn upvote(main: &Client, sidechain: Sidechain) -> Result<()> {
+    main.send_request::<String>(
+        "setbwtwithdrawalvote",
+        &[json!(sidechain.number()), json!("ack")],
+    )?;
+    println!("upvoted the active bundle for {}", sidechain);
+    Ok(())
+}
+
+/// Withdraw support for the active bundle for `sidechain`, letting it age
+/// out instead of accumulating further ACKs.
+pub fn downvote(main: &Client, sidechain: Sidechain) -> Result<()> {
+    main.send_request::<String>(
+        "setbwtwithdrawalvote",
+        &[json!(sidechain.number()), json!("nack")],
+    )?;
+    println!("downvoted the active bundle for {}", sidechain);
+    Ok(())
+}
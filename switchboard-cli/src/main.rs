@@ -1,4 +1,8 @@
 mod amount;
+mod bundle;
+mod swap;
+mod track;
+mod wallet;
 use amount::AmountBtc;
 use anyhow::Result;
 use clap::{Parser, Subcommand};
@@ -78,6 +82,111 @@ enum Commands {
         #[arg(value_parser = btc_amount_parser)]
         fee: Option<bitcoin::Amount>,
     },
+    /// Trustlessly exchange value between sidechains via a hash-time-locked contract
+    Swap {
+        #[command(subcommand)]
+        command: SwapCommand,
+    },
+    /// Inspect and vote on sidechain withdrawal bundles
+    Bundle {
+        #[command(subcommand)]
+        command: BundleCommand,
+    },
+    /// Wait for a deposit to confirm and emit an SPV inclusion proof for it
+    Track {
+        /// Sidechain the deposit credits
+        sidechain: Sidechain,
+        /// Mainchain txid of the deposit
+        txid: bitcoin::Txid,
+        /// Confirmations required before reporting the credited balance
+        #[arg(long, default_value_t = 6)]
+        confirmations: usize,
+    },
+    /// Manage the built-in HD wallet
+    Wallet {
+        #[command(subcommand)]
+        command: WalletCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum WalletCommand {
+    /// Generate a new BIP39 wallet and print its mnemonic for backup
+    Init,
+    /// Restore a wallet from an existing BIP39 mnemonic
+    Restore { mnemonic: String },
+    /// Print the next derived address for a sidechain, for manual funding;
+    /// `deposit`/`withdraw` still go through the daemons, not this wallet
+    Address { sidechain: Sidechain },
+}
+
+/// Password protecting the wallet's encrypted seed at rest. Read from the
+/// environment like the daemons' own `rpcpassword` rather than prompted
+/// interactively, so `switchboard` stays scriptable.
+fn wallet_password() -> Result<String> {
+    std::env::var("SWITCHBOARD_WALLET_PASSWORD")
+        .map_err(|_| anyhow::Error::msg("set SWITCHBOARD_WALLET_PASSWORD to unlock the wallet"))
+}
+
+#[derive(Subcommand, Debug)]
+enum BundleCommand {
+    /// Summarize the active bundle across all sidechains
+    Status,
+    /// Show the full proposed bundle for one sidechain
+    Show { sidechain: Sidechain },
+    /// Cast an M4 ACK vote in favor of the active bundle
+    Upvote { sidechain: Sidechain },
+    /// Withdraw support for the active bundle, letting it age out
+    Downvote { sidechain: Sidechain },
+}
+
+#[derive(Subcommand, Debug)]
+enum SwapCommand {
+    /// Pick a secret and lock funds on `chain_a`, starting a new swap
+    Init {
+        /// Sidechain to lock funds on first
+        chain_a: Sidechain,
+        /// Sidechain the counterparty will lock matching funds on
+        chain_b: Sidechain,
+        /// Amount of BTC to lock
+        #[arg(value_parser = btc_amount_parser)]
+        amount: bitcoin::Amount,
+        /// Blocks until the lock on chain A can be refunded
+        timeout_a: u32,
+    },
+    /// Lock matching funds against a secret hash received from the initiator
+    Accept {
+        /// Sidechain to lock funds on
+        chain_b: Sidechain,
+        /// Sidechain the initiator locked funds on
+        chain_a: Sidechain,
+        /// Secret hash published by the initiator, as hex
+        secret_hash: String,
+        /// Amount of BTC to lock
+        #[arg(value_parser = btc_amount_parser)]
+        amount: bitcoin::Amount,
+        /// Timeout the initiator used for chain A, in blocks
+        timeout_a: u32,
+        /// Blocks until this lock on chain B can be refunded; must be less than timeout_a
+        timeout_b: u32,
+    },
+    /// Continue an interrupted swap: claim as the initiator, or as the counterparty once the preimage is revealed
+    Resume {
+        /// Secret hash identifying the swap, as hex
+        secret_hash: String,
+    },
+    /// Reclaim funds from a swap whose timeout has elapsed unclaimed
+    Refund {
+        /// Secret hash identifying the swap, as hex
+        secret_hash: String,
+    },
+}
+
+fn parse_secret_hash(s: &str) -> Result<[u8; 32]> {
+    let bytes = hex::decode(s)?;
+    bytes
+        .try_into()
+        .map_err(|_| anyhow::Error::msg("secret hash must be 32 bytes"))
 }
 
 fn main() -> Result<()> {
@@ -252,6 +361,93 @@ fn main() -> Result<()> {
                 amount, sidechain, fee
             );
         }
+        Commands::Swap { command } => match command {
+            SwapCommand::Init {
+                chain_a,
+                chain_b,
+                amount,
+                timeout_a,
+            } => swap::init(
+                &datadir, &config, &zcash, &web3, chain_a, chain_b, amount, timeout_a,
+            )?,
+            SwapCommand::Accept {
+                chain_b,
+                chain_a,
+                secret_hash,
+                amount,
+                timeout_a,
+                timeout_b,
+            } => swap::accept(
+                &datadir,
+                &config,
+                &zcash,
+                &web3,
+                chain_b,
+                chain_a,
+                parse_secret_hash(&secret_hash)?,
+                amount,
+                timeout_a,
+                timeout_b,
+            )?,
+            SwapCommand::Resume { secret_hash } => swap::resume(
+                &datadir,
+                &config,
+                &zcash,
+                &web3,
+                parse_secret_hash(&secret_hash)?,
+            )?,
+            SwapCommand::Refund { secret_hash } => swap::refund(
+                &datadir,
+                &config,
+                &zcash,
+                &web3,
+                parse_secret_hash(&secret_hash)?,
+            )?,
+        },
+        Commands::Bundle { command } => match command {
+            BundleCommand::Status => bundle::status(&main)?,
+            BundleCommand::Show { sidechain } => bundle::show(&main, sidechain)?,
+            BundleCommand::Upvote { sidechain } => bundle::upvote(&main, sidechain)?,
+            BundleCommand::Downvote { sidechain } => bundle::downvote(&main, sidechain)?,
+        },
+        Commands::Track {
+            sidechain,
+            txid,
+            confirmations,
+        } => {
+            let sidechain_balance = || -> Result<bitcoin::Amount> {
+                match sidechain {
+                    Sidechain::Zcash => Ok(*zcash.send_request::<AmountBtc>("getbalance", &[])?),
+                    Sidechain::Ethereum => {
+                        pub const SATOSHI: u64 = 10_000_000_000;
+                        let accounts = block_on(web3.eth().accounts())?;
+                        let mut balance = U256::zero();
+                        for account in accounts.iter() {
+                            balance += block_on(web3.eth().balance(*account, None))?;
+                        }
+                        let sat = (balance / SATOSHI).as_u64();
+                        Ok(bitcoin::Amount::from_sat(sat))
+                    }
+                }
+            };
+            track::wait(&main, sidechain_balance, txid, confirmations)?;
+        }
+        Commands::Wallet { command } => match command {
+            WalletCommand::Init => wallet::init(&datadir, &wallet_password()?)?,
+            WalletCommand::Restore { mnemonic } => {
+                wallet::restore(&datadir, &mnemonic, &wallet_password()?)?
+            }
+            WalletCommand::Address { sidechain } => {
+                let password = wallet_password()?;
+                let address = match sidechain {
+                    Sidechain::Zcash => wallet::next_zcash_deposit_address(&datadir, &password)?,
+                    Sidechain::Ethereum => {
+                        format!("{:#x}", wallet::ethereum_address(&datadir, &password)?)
+                    }
+                };
+                println!("{}", address);
+            }
+        },
     }
     Ok(())
 }
@@ -0,0 +1,440 @@
+//! Cross-sidechain atomic swaps using hash-time-locked contracts (HTLCs).
+//!
+//! A swap moves value between the Zcash and Ethereum sidechains directly,
+//! without routing through the mainchain deposit/withdraw flow. The
+//! initiator picks a random preimage `s`, computes `H = sha256(s)`, and
+//! locks funds on chain A spendable by the counterparty against `s` or
+//! refundable to the initiator after `timeout_a`. The counterparty locks
+//! matching funds on chain B under the same `H`, refundable to themselves
+//! after `timeout_b`, where `timeout_b` must be strictly less than
+//! `timeout_a` so the counterparty (who claims second) is never left
+//! without time to react once `s` is revealed. The initiator claims chain
+//! B by publishing `s`; the counterparty observes it on-chain and reuses
+//! it to claim chain A. Swap state is kept as JSON under `datadir` so an
+//! interrupted swap can be resumed or refunded later with `swap resume`/
+//! `swap refund`.
+
+use crate::Sidechain;
+use anyhow::{anyhow, Result};
+use futures::executor::block_on;
+use hex::ToHex;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use switchboard::config::Config;
+use ureq_jsonrpc::json;
+use web3::contract::{Contract, Options};
+use web3::transports::Http;
+use web3::types::{Address, FilterBuilder, H256, U256};
+use web3::Web3;
+
+/// ABI of the HTLC contract deployed on the Ethereum sidechain.
+const HTLC_ABI: &[u8] = include_bytes!("htlc_abi.json");
+
+/// Wei per satoshi, matching the rest of the repo's sat/wei convention
+/// (see `Getbalances`'s own `SATOSHI` constant in `main.rs`).
+const SATOSHI: u64 = 10_000_000_000;
+
+/// Which side of the swap the local operator is playing. The initiator
+/// locks first and uses the longer timeout; the counterparty locks second,
+/// claims second, and must use the shorter timeout.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Role {
+    Initiator,
+    Counterparty,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SwapState {
+    pub role: Role,
+    pub chain_a: Sidechain,
+    pub chain_b: Sidechain,
+    pub secret_hash: [u8; 32],
+    pub preimage: Option<[u8; 32]>,
+    pub timeout_a: u32,
+    pub timeout_b: u32,
+    pub txid_a: Option<String>,
+    pub txid_b: Option<String>,
+    pub claimed: bool,
+    pub refunded: bool,
+}
+
+fn swaps_dir(datadir: &Path) -> PathBuf {
+    datadir.join("swaps")
+}
+
+fn state_path(datadir: &Path, secret_hash: &[u8; 32]) -> PathBuf {
+    swaps_dir(datadir).join(format!("{}.json", secret_hash.encode_hex::<String>()))
+}
+
+fn load(datadir: &Path, secret_hash: &[u8; 32]) -> Result<SwapState> {
+    let path = state_path(datadir, secret_hash);
+    let contents = fs::read_to_string(&path).map_err(|_| {
+        anyhow!(
+            "no swap found for secret hash {}",
+            secret_hash.encode_hex::<String>()
+        )
+    })?;
+    Ok(serde_json::from_str(&contents)?)
+}
+
+fn save(datadir: &Path, state: &SwapState) -> Result<()> {
+    fs::create_dir_all(swaps_dir(datadir))?;
+    fs::write(
+        state_path(datadir, &state.secret_hash),
+        serde_json::to_string_pretty(state)?,
+    )?;
+    Ok(())
+}
+
+fn htlc_contract(web3: &Web3<Http>, config: &Config) -> Result<Contract<Http>> {
+    let address: Address = config.ethereum.htlc_contract_address.parse()?;
+    Ok(Contract::from_json(web3.eth(), address, HTLC_ABI)?)
+}
+
+/// Lock funds on `chain_a`, picking a fresh preimage, and persist swap
+/// state so the swap can be resumed if switchboard is restarted before the
+/// counterparty locks their side.
+#[allow(clippy::too_many_arguments)]
+pub fn init(
+    datadir: &Path,
+    config: &Config,
+    zcash: &ureq_jsonrpc::Client,
+    web3: &Web3<Http>,
+    chain_a: Sidechain,
+    chain_b: Sidechain,
+    amount: bitcoin::Amount,
+    timeout_a: u32,
+) -> Result<()> {
+    let mut preimage = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut preimage);
+    let secret_hash: [u8; 32] = Sha256::digest(preimage).into();
+
+    let txid_a = match chain_a {
+        Sidechain::Zcash => zcash.send_request::<String>(
+            "createhtlc",
+            &[
+                json!(crate::amount::AmountBtc(amount)),
+                json!(secret_hash.encode_hex::<String>()),
+                json!(timeout_a),
+            ],
+        )?,
+        Sidechain::Ethereum => {
+            let contract = htlc_contract(web3, config)?;
+            let accounts = block_on(web3.eth().accounts())?;
+            let account = *accounts
+                .first()
+                .ok_or_else(|| anyhow!("No available Ethereum addresses"))?;
+            // The counterparty claims chain A (by reusing the preimage the
+            // initiator reveals on chain B), so they must be the one able
+            // to spend this lock.
+            let claimant: Address = config.ethereum.counterparty_address.parse()?;
+            let options = Options {
+                value: Some(U256::from(amount.to_sat()) * U256::from(SATOSHI)),
+                ..Default::default()
+            };
+            let receipt = block_on(contract.call_with_confirmations(
+                "lock",
+                (secret_hash, claimant, U256::from(timeout_a)),
+                account,
+                options,
+                1,
+            ))?;
+            format!("{:#x}", receipt.transaction_hash)
+        }
+    };
+
+    let state = SwapState {
+        role: Role::Initiator,
+        chain_a,
+        chain_b,
+        secret_hash,
+        preimage: Some(preimage),
+        timeout_a,
+        timeout_b: 0,
+        txid_a: Some(txid_a.clone()),
+        txid_b: None,
+        claimed: false,
+        refunded: false,
+    };
+    save(datadir, &state)?;
+
+    println!(
+        "locked {} on {} with secret hash {} (txid {}); give this hash to the counterparty",
+        amount,
+        chain_a,
+        secret_hash.encode_hex::<String>(),
+        txid_a
+    );
+    Ok(())
+}
+
+/// Lock matching funds on `chain_b` against a secret hash received from the
+/// initiator. `timeout_b` must be strictly less than `timeout_a` so the
+/// counterparty, who claims second, still has time to react once the
+/// initiator reveals the preimage.
+#[allow(clippy::too_many_arguments)]
+pub fn accept(
+    datadir: &Path,
+    config: &Config,
+    zcash: &ureq_jsonrpc::Client,
+    web3: &Web3<Http>,
+    chain_b: Sidechain,
+    chain_a: Sidechain,
+    secret_hash: [u8; 32],
+    amount: bitcoin::Amount,
+    timeout_a: u32,
+    timeout_b: u32,
+) -> Result<()> {
+    if timeout_b >= timeout_a {
+        return Err(anyhow!(
+            "counterparty timeout ({}) must be strictly less than the initiator's timeout ({})",
+            timeout_b,
+            timeout_a
+        ));
+    }
+
+    let txid_b = match chain_b {
+        Sidechain::Zcash => zcash.send_request::<String>(
+            "createhtlc",
+            &[
+                json!(crate::amount::AmountBtc(amount)),
+                json!(secret_hash.encode_hex::<String>()),
+                json!(timeout_b),
+            ],
+        )?,
+        Sidechain::Ethereum => {
+            let contract = htlc_contract(web3, config)?;
+            let accounts = block_on(web3.eth().accounts())?;
+            let account = *accounts
+                .first()
+                .ok_or_else(|| anyhow!("No available Ethereum addresses"))?;
+            // The initiator claims chain B, so they must be the one able
+            // to spend this lock against the preimage.
+            let claimant: Address = config.ethereum.initiator_address.parse()?;
+            let options = Options {
+                value: Some(U256::from(amount.to_sat()) * U256::from(SATOSHI)),
+                ..Default::default()
+            };
+            let receipt = block_on(contract.call_with_confirmations(
+                "lock",
+                (secret_hash, claimant, U256::from(timeout_b)),
+                account,
+                options,
+                1,
+            ))?;
+            format!("{:#x}", receipt.transaction_hash)
+        }
+    };
+
+    let state = SwapState {
+        role: Role::Counterparty,
+        chain_a,
+        chain_b,
+        secret_hash,
+        preimage: None,
+        timeout_a,
+        timeout_b,
+        txid_a: None,
+        txid_b: Some(txid_b.clone()),
+        claimed: false,
+        refunded: false,
+    };
+    save(datadir, &state)?;
+
+    println!(
+        "locked {} on {} with secret hash {} (txid {})",
+        amount,
+        chain_b,
+        secret_hash.encode_hex::<String>(),
+        txid_b
+    );
+    Ok(())
+}
+
+/// Continue an interrupted swap: the initiator claims chain B (revealing
+/// the preimage), or, if the counterparty has already locked and the
+/// preimage has since been revealed on-chain, the counterparty claims
+/// chain A with it.
+pub fn resume(
+    datadir: &Path,
+    config: &Config,
+    zcash: &ureq_jsonrpc::Client,
+    web3: &Web3<Http>,
+    secret_hash: [u8; 32],
+) -> Result<()> {
+    let mut state = load(datadir, &secret_hash)?;
+    if state.claimed || state.refunded {
+        println!(
+            "swap {} already settled",
+            secret_hash.encode_hex::<String>()
+        );
+        return Ok(());
+    }
+
+    match state.role {
+        Role::Initiator => {
+            let preimage = state
+                .preimage
+                .ok_or_else(|| anyhow!("initiator state is missing its own preimage"))?;
+            claim(config, zcash, web3, state.chain_b, secret_hash, preimage)?;
+            state.claimed = true;
+            save(datadir, &state)?;
+            println!(
+                "claimed chain B, revealing preimage {}; the counterparty can now claim chain A",
+                preimage.encode_hex::<String>()
+            );
+        }
+        Role::Counterparty => {
+            let preimage = find_revealed_preimage(config, zcash, web3, state.chain_b, secret_hash)?
+                .ok_or_else(|| {
+                    anyhow!("preimage has not been revealed on chain B yet, try again later")
+                })?;
+            claim(config, zcash, web3, state.chain_a, secret_hash, preimage)?;
+            state.preimage = Some(preimage);
+            state.claimed = true;
+            save(datadir, &state)?;
+            println!("claimed chain A using revealed preimage");
+        }
+    }
+    Ok(())
+}
+
+/// Reclaim the local side's lock (chain A for the initiator, chain B for
+/// the counterparty) once its timeout has elapsed without the other party
+/// claiming it.
+pub fn refund(
+    datadir: &Path,
+    config: &Config,
+    zcash: &ureq_jsonrpc::Client,
+    web3: &Web3<Http>,
+    secret_hash: [u8; 32],
+) -> Result<()> {
+    let mut state = load(datadir, &secret_hash)?;
+    if state.claimed || state.refunded {
+        println!(
+            "swap {} already settled",
+            secret_hash.encode_hex::<String>()
+        );
+        return Ok(());
+    }
+
+    let chain = match state.role {
+        Role::Initiator => state.chain_a,
+        Role::Counterparty => state.chain_b,
+    };
+    match chain {
+        Sidechain::Zcash => {
+            zcash.send_request::<String>(
+                "refundhtlc",
+                &[json!(secret_hash.encode_hex::<String>())],
+            )?;
+        }
+        Sidechain::Ethereum => {
+            let contract = htlc_contract(web3, config)?;
+            let accounts = block_on(web3.eth().accounts())?;
+            let account = *accounts
+                .first()
+                .ok_or_else(|| anyhow!("No available Ethereum addresses"))?;
+            block_on(contract.call_with_confirmations(
+                "refund",
+                (secret_hash,),
+                account,
+                Options::default(),
+                1,
+            ))?;
+        }
+    }
+
+    state.refunded = true;
+    save(datadir, &state)?;
+    println!("refunded swap {}", secret_hash.encode_hex::<String>());
+    Ok(())
+}
+
+fn claim(
+    config: &Config,
+    zcash: &ureq_jsonrpc::Client,
+    web3: &Web3<Http>,
+    chain: Sidechain,
+    secret_hash: [u8; 32],
+    preimage: [u8; 32],
+) -> Result<()> {
+    match chain {
+        Sidechain::Zcash => {
+            zcash.send_request::<String>(
+                "claimhtlc",
+                &[
+                    json!(secret_hash.encode_hex::<String>()),
+                    json!(preimage.encode_hex::<String>()),
+                ],
+            )?;
+        }
+        Sidechain::Ethereum => {
+            let contract = htlc_contract(web3, config)?;
+            let accounts = block_on(web3.eth().accounts())?;
+            let account = *accounts
+                .first()
+                .ok_or_else(|| anyhow!("No available Ethereum addresses"))?;
+            block_on(contract.call_with_confirmations(
+                "claim",
+                (secret_hash, preimage),
+                account,
+                Options::default(),
+                1,
+            ))?;
+        }
+    }
+    Ok(())
+}
+
+/// Look for the preimage having been revealed by a claim transaction on
+/// `chain`, mirroring how the counterparty watches the initiator's claim in
+/// the live protocol.
+fn find_revealed_preimage(
+    config: &Config,
+    zcash: &ureq_jsonrpc::Client,
+    web3: &Web3<Http>,
+    chain: Sidechain,
+    secret_hash: [u8; 32],
+) -> Result<Option<[u8; 32]>> {
+    match chain {
+        Sidechain::Zcash => {
+            let preimage = zcash.send_request::<Option<String>>(
+                "gethtlcpreimage",
+                &[json!(secret_hash.encode_hex::<String>())],
+            )?;
+            preimage
+                .map(|hex_string| {
+                    let bytes = hex::decode(hex_string)?;
+                    let array: [u8; 32] = bytes
+                        .try_into()
+                        .map_err(|_| anyhow!("preimage from node was not 32 bytes"))?;
+                    Ok(array)
+                })
+                .transpose()
+        }
+        Sidechain::Ethereum => {
+            let contract = htlc_contract(web3, config)?;
+            let claimed_event = contract.abi().event("Claimed")?;
+            let filter = FilterBuilder::default()
+                .address(vec![contract.address()])
+                .topics(
+                    Some(vec![claimed_event.signature()]),
+                    Some(vec![H256::from(secret_hash)]),
+                    None,
+                    None,
+                )
+                .build();
+            let logs = block_on(web3.eth().logs(filter))?;
+            let preimage = logs.first().map(|log| {
+                let mut preimage = [0u8; 32];
+                preimage.copy_from_slice(&log.data.0[0..32]);
+                preimage
+            });
+            Ok(preimage)
+        }
+    }
+}
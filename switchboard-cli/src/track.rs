@@ -0,0 +1,140 @@
+//! Deposit confirmation tracking and SPV proof generation.
+//!
+//! After `Deposit` returns a txid there is no way to know when it has
+//! matured on the target sidechain. `track` polls the mainchain for the
+//! transaction's confirmation depth and, once it reaches `--confirmations`,
+//! reports the sidechain balance it credited. Alongside that it emits a
+//! merkle inclusion proof connecting the txid to its block's merkle root,
+//! the same structure light clients use to confirm a transaction is buried
+//! under a given header without trusting the node's word for it.
+
+use anyhow::{anyhow, Result};
+use bitcoin::consensus::encode::deserialize;
+use bitcoin::hashes::{sha256d, Hash};
+use bitcoin::{Block, BlockHash, Txid};
+use serde::{Deserialize, Serialize};
+use std::thread::sleep;
+use std::time::Duration;
+use ureq_jsonrpc::{json, Client};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Deserialize)]
+struct RawTransactionVerbose {
+    blockhash: Option<BlockHash>,
+}
+
+#[derive(Deserialize)]
+struct BlockHeaderVerbose {
+    height: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MerkleProof {
+    pub txid: Txid,
+    pub block_hash: BlockHash,
+    pub block_height: usize,
+    pub merkle_root: String,
+    pub index: usize,
+    pub branch: Vec<String>,
+}
+
+fn find_confirming_block(main: &Client, txid: Txid) -> Result<Option<(Block, usize)>> {
+    let tx = main
+        .send_request::<RawTransactionVerbose>("getrawtransaction", &[json!(txid), json!(true)])?;
+    let Some(block_hash) = tx.blockhash else {
+        return Ok(None);
+    };
+    let header = main
+        .send_request::<BlockHeaderVerbose>("getblockheader", &[json!(block_hash), json!(true)])?;
+    let block_hex = main.send_request::<String>("getblock", &[json!(block_hash), json!(0)])?;
+    let block: Block = deserialize(&hex::decode(block_hex)?)?;
+    Ok(Some((block, header.height)))
+}
+
+fn merkle_branch(
+    leaves: &[sha256d::Hash],
+    mut index: usize,
+) -> (Vec<sha256d::Hash>, sha256d::Hash) {
+    let mut branch = Vec::new();
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        if level.len() % 2 == 1 {
+            let last = *level.last().unwrap();
+            level.push(last);
+        }
+        let sibling = if index % 2 == 0 { index + 1 } else { index - 1 };
+        branch.push(level[sibling]);
+        level = level
+            .chunks_exact(2)
+            .map(|pair| {
+                let mut bytes = Vec::with_capacity(64);
+                bytes.extend_from_slice(&pair[0][..]);
+                bytes.extend_from_slice(&pair[1][..]);
+                sha256d::Hash::hash(&bytes)
+            })
+            .collect();
+        index /= 2;
+    }
+    (branch, level[0])
+}
+
+/// Build an SPV merkle inclusion proof for `txid` within `block`.
+pub fn prove_inclusion(
+    block: &Block,
+    block_hash: BlockHash,
+    height: usize,
+    txid: Txid,
+) -> Result<MerkleProof> {
+    let leaves: Vec<sha256d::Hash> = block
+        .txdata
+        .iter()
+        .map(|tx| tx.txid().as_raw_hash().to_owned())
+        .collect();
+    let index = block
+        .txdata
+        .iter()
+        .position(|tx| tx.txid() == txid)
+        .ok_or_else(|| anyhow!("txid not found in its own confirming block"))?;
+    let (branch, root) = merkle_branch(&leaves, index);
+    Ok(MerkleProof {
+        txid,
+        block_hash,
+        block_height: height,
+        merkle_root: root.to_string(),
+        index,
+        branch: branch.iter().map(|hash| hash.to_string()).collect(),
+    })
+}
+
+/// Poll the mainchain until `txid` has `confirmations` confirmations,
+/// printing depth as it grows, then print the resulting merkle proof and
+/// the sidechain balance it credited.
+pub fn wait(
+    main: &Client,
+    sidechain_balance: impl Fn() -> Result<bitcoin::Amount>,
+    txid: Txid,
+    confirmations: usize,
+) -> Result<()> {
+    loop {
+        if let Some((block, height)) = find_confirming_block(main, txid)? {
+            let tip = main.send_request::<usize>("getblockcount", &[])?;
+            let depth = tip.saturating_sub(height) + 1;
+            println!(
+                "{}/{} confirmations",
+                depth.min(confirmations),
+                confirmations
+            );
+            if depth >= confirmations {
+                let block_hash = block.block_hash();
+                let proof = prove_inclusion(&block, block_hash, height, txid)?;
+                println!("{}", serde_json::to_string_pretty(&proof)?);
+                println!("credited balance: {}", sidechain_balance()?);
+                return Ok(());
+            }
+        } else {
+            println!("{} not yet seen on the mainchain", txid);
+        }
+        sleep(POLL_INTERVAL);
+    }
+}
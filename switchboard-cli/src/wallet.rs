@@ -0,0 +1,192 @@
+//! Built-in HD wallet.
+//!
+//! Derives every sidechain key from a single BIP39 mnemonic instead of
+//! scattering custody across each daemon's own `getnewaddress`/
+//! `eth.accounts`: Zcash deposit addresses come from a `m/44'/133'` path
+//! rooted at the seed, base58check-encoded with Zcash's transparent-address
+//! version bytes (a Zcash t-address is not a Bitcoin bech32 address even
+//! though the same secp256k1 keys underlie both), and Ethereum keys come
+//! from the standard `m/44'/60'/0'/0` path off the same seed. The seed is
+//! stored encrypted under `datadir` so it survives restarts without ever
+//! touching disk in the clear.
+//!
+//! This wallet does not yet custody funds end to end: `switchboard-cli`
+//! only exposes it through `wallet address`, for an operator to fund or
+//! sweep manually. `deposit`/`withdraw` still route through the mainchain
+//! and sidechain daemons, which cannot source or sign for these
+//! HD-derived keys, so wiring them in would silently strand funds.
+//! Turning this into a real deposit/withdraw backend needs local UTXO
+//! tracking and transaction signing for Zcash and a signing Ethereum
+//! transport, neither of which exists here yet.
+
+use anyhow::{anyhow, Result};
+use bip39::Mnemonic;
+use bitcoin::bip32::{DerivationPath, Xpriv};
+use bitcoin::hashes::{hash160, Hash};
+use bitcoin::secp256k1::{Secp256k1, SecretKey};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+const ZCASH_DEPOSIT_PATH: &str = "m/44'/133'/0'/0";
+const ETHEREUM_PATH: &str = "m/44'/60'/0'/0";
+
+/// Zcash mainnet transparent P2PKH address version bytes (`t1...`).
+const ZCASH_T_ADDR_PREFIX: [u8; 2] = [0x1c, 0xb8];
+
+fn wallet_path(datadir: &Path) -> PathBuf {
+    datadir.join("wallet.dat")
+}
+
+/// Whether a wallet has been initialized under `datadir`.
+pub fn wallet_exists(datadir: &Path) -> bool {
+    wallet_path(datadir).exists()
+}
+
+/// The on-disk, encrypted wallet file. `nonce`/`ciphertext` protect the
+/// BIP39 mnemonic phrase; `next_index` tracks how many deposit addresses have been
+/// handed out so each one is only ever derived once.
+#[derive(Serialize, Deserialize)]
+struct EncryptedWallet {
+    nonce: [u8; 12],
+    ciphertext: Vec<u8>,
+    next_index: u32,
+}
+
+fn key_from_password(password: &str) -> [u8; 32] {
+    // The password is the only secret protecting the seed at rest; hash it
+    // once with a fixed-size output so it can key ChaCha20-Poly1305
+    // directly, the same tradeoff the daemons' own `rpcpassword` makes.
+    use bitcoin::hashes::{sha256, Hash};
+    sha256::Hash::hash(password.as_bytes()).to_byte_array()
+}
+
+fn save(datadir: &Path, mnemonic: &Mnemonic, password: &str, next_index: u32) -> Result<()> {
+    let key = key_from_password(password);
+    let cipher = ChaCha20Poly1305::new((&key).into());
+    let mut nonce_bytes = [0u8; 12];
+    rand::thread_rng().fill_bytes(&mut nonce_bytes);
+    let ciphertext = cipher
+        .encrypt(
+            Nonce::from_slice(&nonce_bytes),
+            mnemonic.to_string().as_bytes(),
+        )
+        .map_err(|_| anyhow!("failed to encrypt wallet seed"))?;
+    let wallet = EncryptedWallet {
+        nonce: nonce_bytes,
+        ciphertext,
+        next_index,
+    };
+    fs::create_dir_all(datadir)?;
+    fs::write(wallet_path(datadir), serde_json::to_string_pretty(&wallet)?)?;
+    Ok(())
+}
+
+fn load_mnemonic(datadir: &Path, password: &str) -> Result<(Mnemonic, u32)> {
+    let contents = fs::read_to_string(wallet_path(datadir)).map_err(|_| {
+        anyhow!(
+            "no wallet found under {}; run `wallet init` first",
+            datadir.display()
+        )
+    })?;
+    let wallet: EncryptedWallet = serde_json::from_str(&contents)?;
+    let key = key_from_password(password);
+    let cipher = ChaCha20Poly1305::new((&key).into());
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(&wallet.nonce), wallet.ciphertext.as_ref())
+        .map_err(|_| anyhow!("wrong password or corrupted wallet file"))?;
+    let mnemonic = Mnemonic::parse(std::str::from_utf8(&plaintext)?)?;
+    Ok((mnemonic, wallet.next_index))
+}
+
+fn master_key(mnemonic: &Mnemonic, network: bitcoin::Network) -> Result<Xpriv> {
+    let seed = mnemonic.to_seed("");
+    Ok(Xpriv::new_master(network, &seed)?)
+}
+
+/// Generate a fresh 24-word mnemonic, print it once for the user to back
+/// up, and persist it encrypted under `datadir`.
+pub fn init(datadir: &Path, password: &str) -> Result<()> {
+    if wallet_exists(datadir) {
+        return Err(anyhow!(
+            "a wallet already exists under {}",
+            datadir.display()
+        ));
+    }
+    let mut entropy = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut entropy);
+    let mnemonic = Mnemonic::from_entropy(&entropy)?;
+    save(datadir, &mnemonic, password, 0)?;
+    println!("write this down, it is the only backup of your wallet:");
+    println!("{}", mnemonic);
+    Ok(())
+}
+
+/// Restore a wallet from a previously generated mnemonic.
+pub fn restore(datadir: &Path, mnemonic: &str, password: &str) -> Result<()> {
+    if wallet_exists(datadir) {
+        return Err(anyhow!(
+            "a wallet already exists under {}",
+            datadir.display()
+        ));
+    }
+    let mnemonic = Mnemonic::parse(mnemonic)?;
+    save(datadir, &mnemonic, password, 0)?;
+    println!("wallet restored");
+    Ok(())
+}
+
+/// Base58check-encode a 20-byte pubkey hash with Zcash's transparent
+/// P2PKH version bytes; a Zcash t-address is base58, not Bitcoin's bech32.
+fn zcash_t_address(pubkey_hash: &[u8; 20]) -> String {
+    let mut payload = Vec::with_capacity(ZCASH_T_ADDR_PREFIX.len() + pubkey_hash.len());
+    payload.extend_from_slice(&ZCASH_T_ADDR_PREFIX);
+    payload.extend_from_slice(pubkey_hash);
+    bs58::encode(payload).with_check().into_string()
+}
+
+/// Derive the next unused Zcash deposit address and advance the index so
+/// it is never handed out again. The network argument to `Xpriv` only
+/// affects its own (unused) serialization prefix, not the keys it
+/// derives, so the master key is always derived as if for mainnet and the
+/// resulting pubkey hash is encoded with Zcash's own address version
+/// bytes rather than Bitcoin's.
+pub fn next_zcash_deposit_address(datadir: &Path, password: &str) -> Result<String> {
+    let (mnemonic, index) = load_mnemonic(datadir, password)?;
+    let master = master_key(&mnemonic, bitcoin::Network::Bitcoin)?;
+    let secp = Secp256k1::new();
+    let path = DerivationPath::from_str(&format!("{}/{}", ZCASH_DEPOSIT_PATH, index))?;
+    let child = master.derive_priv(&secp, &path)?;
+    let public_key = child.private_key.public_key(&secp);
+    let pubkey_hash = hash160::Hash::hash(&public_key.serialize()).to_byte_array();
+    let address = zcash_t_address(&pubkey_hash);
+    save(datadir, &mnemonic, password, index + 1)?;
+    Ok(address)
+}
+
+/// Derive the Ethereum secret key at `m/44'/60'/0'/0/0`, from which
+/// [`ethereum_address`] is computed. Withdrawals do not sign with this key;
+/// they still use geth's account list.
+pub fn ethereum_key(datadir: &Path, password: &str) -> Result<SecretKey> {
+    let (mnemonic, _) = load_mnemonic(datadir, password)?;
+    let master = master_key(&mnemonic, bitcoin::Network::Bitcoin)?;
+    let secp = Secp256k1::new();
+    let path = DerivationPath::from_str(&format!("{}/0", ETHEREUM_PATH))?;
+    let child = master.derive_priv(&secp, &path)?;
+    Ok(child.private_key)
+}
+
+/// Derive the Ethereum address for [`ethereum_key`]: the low 20 bytes of
+/// the Keccak-256 hash of its uncompressed public key.
+pub fn ethereum_address(datadir: &Path, password: &str) -> Result<web3::types::Address> {
+    let secp = Secp256k1::new();
+    let public_key = ethereum_key(datadir, password)?.public_key(&secp);
+    let uncompressed = public_key.serialize_uncompressed();
+    let hash = Keccak256::digest(&uncompressed[1..]);
+    Ok(web3::types::Address::from_slice(&hash[12..]))
+}
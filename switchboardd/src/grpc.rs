@@ -0,0 +1,293 @@
+//! gRPC control API.
+//!
+//! Exposes the core operations of `switchboard-cli` (balances, block
+//! counts, deposit, withdraw, refund, bundle status) over a tonic-served,
+//! protobuf-defined service so switchboard can be embedded in
+//! orchestration or GUI tooling instead of being reachable only through
+//! the `clap` CLI. The service reuses the same `Config`-driven RPC clients
+//! the CLI builds and returns typed messages (amounts in satoshis,
+//! sidechain enum, txids) rather than CLI stdout.
+
+pub mod pb {
+    tonic::include_proto!("switchboard");
+}
+
+use anyhow::Result;
+use pb::switchboard_server::{Switchboard, SwitchboardServer};
+use pb::{
+    BundleEntry, BundleStatusReply, BundleStatusRequest, DepositReply, DepositRequest,
+    GetBalancesReply, GetBalancesRequest, GetBlockCountsReply, GetBlockCountsRequest, RefundReply,
+    RefundRequest, Sidechain as PbSidechain, WithdrawReply, WithdrawRequest,
+};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use switchboard::config::Config;
+use tonic::{transport::Server, Request, Response, Status};
+use ureq_jsonrpc::json;
+use web3::types::U256;
+use web3::Transport;
+
+#[derive(Deserialize)]
+struct Bundle {
+    #[serde(rename = "bundlehash")]
+    hash: String,
+    #[serde(rename = "workscore")]
+    ack_count: u16,
+    #[serde(rename = "blocksleft")]
+    blocks_left: u16,
+}
+
+/// BTC-denominated wrapper matching the wire format the mainchain/zcash
+/// RPCs actually expect, mirroring `switchboard-cli`'s own `AmountBtc` so
+/// this service doesn't send or parse raw satoshis against an RPC that
+/// speaks BTC.
+#[derive(Clone, Copy)]
+struct AmountBtc(bitcoin::Amount);
+
+impl Serialize for AmountBtc {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.0.to_btc())
+    }
+}
+
+impl<'de> Deserialize<'de> for AmountBtc {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let btc = f64::deserialize(deserializer)?;
+        Ok(AmountBtc(
+            bitcoin::Amount::from_btc(btc).map_err(serde::de::Error::custom)?,
+        ))
+    }
+}
+
+pub struct SwitchboardService {
+    pub config: Config,
+    pub main: ureq_jsonrpc::Client,
+    pub zcash: ureq_jsonrpc::Client,
+    pub web3: web3::Web3<web3::transports::Http>,
+    pub eth_transport: web3::transports::Http,
+}
+
+fn to_status(err: anyhow::Error) -> Status {
+    Status::internal(err.to_string())
+}
+
+#[tonic::async_trait]
+impl Switchboard for SwitchboardService {
+    async fn get_balances(
+        &self,
+        _request: Request<GetBalancesRequest>,
+    ) -> Result<Response<GetBalancesReply>, Status> {
+        let main_sat = self
+            .main
+            .send_request::<AmountBtc>("getbalance", &[])
+            .map_err(|err| to_status(err.into()))?
+            .0
+            .to_sat();
+        let zcash_sat = self
+            .zcash
+            .send_request::<AmountBtc>("getbalance", &[])
+            .map_err(|err| to_status(err.into()))?
+            .0
+            .to_sat();
+        let ethereum_sat = {
+            const SATOSHI: u64 = 10_000_000_000;
+            let accounts = self
+                .web3
+                .eth()
+                .accounts()
+                .await
+                .map_err(|err| to_status(err.into()))?;
+            let mut balance = U256::zero();
+            for account in accounts.iter() {
+                balance += self
+                    .web3
+                    .eth()
+                    .balance(*account, None)
+                    .await
+                    .map_err(|err| to_status(err.into()))?;
+            }
+            (balance / SATOSHI).as_u64()
+        };
+        Ok(Response::new(GetBalancesReply {
+            main_sat,
+            zcash_sat,
+            ethereum_sat,
+        }))
+    }
+
+    async fn get_block_counts(
+        &self,
+        _request: Request<GetBlockCountsRequest>,
+    ) -> Result<Response<GetBlockCountsReply>, Status> {
+        let main = self
+            .main
+            .send_request::<usize>("getblockcount", &[])
+            .map_err(|err| to_status(err.into()))? as u64;
+        let zcash = self
+            .zcash
+            .send_request::<usize>("getblockcount", &[])
+            .map_err(|err| to_status(err.into()))? as u64;
+        let ethereum = self
+            .web3
+            .eth()
+            .block_number()
+            .await
+            .map_err(|err| to_status(err.into()))?
+            .as_u64();
+        Ok(Response::new(GetBlockCountsReply {
+            main,
+            zcash,
+            ethereum,
+        }))
+    }
+
+    async fn deposit(
+        &self,
+        request: Request<DepositRequest>,
+    ) -> Result<Response<DepositReply>, Status> {
+        let request = request.into_inner();
+        let address = match request.sidechain() {
+            PbSidechain::Zcash => self
+                .zcash
+                .send_request::<String>("getnewaddress", &[])
+                .map_err(|err| to_status(err.into()))?,
+            PbSidechain::Ethereum => {
+                let accounts = self
+                    .web3
+                    .eth()
+                    .accounts()
+                    .await
+                    .map_err(|err| to_status(err.into()))?;
+                let account = accounts.first().ok_or_else(|| {
+                    Status::failed_precondition("no available Ethereum addresses")
+                })?;
+                format!("{:#x}", account)
+            }
+        };
+        let sidechain_number = request.sidechain as usize;
+        let address = switchboard::format_deposit_address(sidechain_number, address);
+        let amount = AmountBtc(bitcoin::Amount::from_sat(request.amount_sat));
+        let fee = AmountBtc(bitcoin::Amount::from_sat(request.fee_sat));
+        let txid = self
+            .main
+            .send_request::<bitcoin::Txid>(
+                "createsidechaindeposit",
+                &[
+                    json!(sidechain_number),
+                    json!(address),
+                    json!(amount),
+                    json!(fee),
+                ],
+            )
+            .map_err(|err| to_status(err.into()))?;
+        Ok(Response::new(DepositReply {
+            txid: txid.to_string(),
+        }))
+    }
+
+    async fn withdraw(
+        &self,
+        request: Request<WithdrawRequest>,
+    ) -> Result<Response<WithdrawReply>, Status> {
+        let request = request.into_inner();
+        match request.sidechain() {
+            PbSidechain::Zcash => {
+                let amount = AmountBtc(bitcoin::Amount::from_sat(request.amount_sat));
+                let fee = AmountBtc(bitcoin::Amount::from_sat(request.fee_sat));
+                self.zcash
+                    .send_request::<String>("withdraw", &[json!(amount), json!(fee)])
+                    .map_err(|err| to_status(err.into()))?;
+            }
+            PbSidechain::Ethereum => {
+                let accounts = self
+                    .web3
+                    .eth()
+                    .accounts()
+                    .await
+                    .map_err(|err| to_status(err.into()))?;
+                let account = accounts.first().ok_or_else(|| {
+                    Status::failed_precondition("no available Ethereum addresses")
+                })?;
+                let account = format!("{:#x}", account);
+                self.eth_transport
+                    .execute(
+                        "eth_withdraw",
+                        vec![
+                            json!(account),
+                            json!(U256::from(request.amount_sat)),
+                            json!(U256::from(request.fee_sat)),
+                        ],
+                    )
+                    .await
+                    .map_err(|err| to_status(err.into()))?;
+            }
+        }
+        Ok(Response::new(WithdrawReply {}))
+    }
+
+    async fn refund(
+        &self,
+        request: Request<RefundRequest>,
+    ) -> Result<Response<RefundReply>, Status> {
+        let request = request.into_inner();
+        match request.sidechain() {
+            PbSidechain::Zcash => {
+                let amount = AmountBtc(bitcoin::Amount::from_sat(request.amount_sat));
+                let fee = AmountBtc(bitcoin::Amount::from_sat(request.fee_sat));
+                self.zcash
+                    .send_request::<String>("refund", &[json!(amount), json!(fee)])
+                    .map_err(|err| to_status(err.into()))?;
+            }
+            PbSidechain::Ethereum => {
+                return Err(Status::unimplemented(
+                    "automatic refunds are not supported for ethereum, use geth-console",
+                ))
+            }
+        }
+        Ok(Response::new(RefundReply {}))
+    }
+
+    async fn bundle_status(
+        &self,
+        _request: Request<BundleStatusRequest>,
+    ) -> Result<Response<BundleStatusReply>, Status> {
+        let mut bundles = Vec::new();
+        for (sidechain, pb_sidechain) in [
+            (0usize, PbSidechain::Zcash),
+            (1usize, PbSidechain::Ethereum),
+        ] {
+            let bundle = self
+                .main
+                .send_request::<Vec<Bundle>>("listwithdrawalstatus", &[json!(sidechain)])
+                .map_err(|err| to_status(err.into()))?
+                .into_iter()
+                .next();
+            bundles.push(match bundle {
+                Some(bundle) => BundleEntry {
+                    sidechain: pb_sidechain as i32,
+                    has_bundle: true,
+                    bundle_hash: bundle.hash,
+                    ack_count: bundle.ack_count as u32,
+                    blocks_left: bundle.blocks_left as u32,
+                },
+                None => BundleEntry {
+                    sidechain: pb_sidechain as i32,
+                    has_bundle: false,
+                    bundle_hash: String::new(),
+                    ack_count: 0,
+                    blocks_left: 0,
+                },
+            });
+        }
+        Ok(Response::new(BundleStatusReply { bundles }))
+    }
+}
+
+/// Serve the gRPC control API on `addr` until the process exits.
+pub async fn serve(addr: SocketAddr, service: SwitchboardService) -> Result<()> {
+    Server::builder()
+        .add_service(SwitchboardServer::new(service))
+        .serve(addr)
+        .await?;
+    Ok(())
+}
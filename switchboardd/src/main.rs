@@ -1,3 +1,6 @@
+mod grpc;
+mod supervisor;
+
 use anyhow::Result;
 use clap::Parser;
 use std::path::PathBuf;
@@ -14,6 +17,13 @@ struct Cli {
     datadir: Option<PathBuf>,
     #[arg(short, long)]
     bin_download_url: Option<String>,
+    /// Disable health checks and auto-restart; just launch the daemons and
+    /// wait for Ctrl-C, as before
+    #[arg(long)]
+    no_supervise: bool,
+    /// Serve the gRPC control API on this port alongside the daemons
+    #[arg(long)]
+    grpc_port: Option<u16>,
 }
 
 fn main() -> Result<()> {
@@ -26,12 +36,61 @@ fn main() -> Result<()> {
     let url = args
         .bin_download_url
         .unwrap_or("http://drivechain.info/releases/bin/bin.tar.gz".to_string());
-    let mut daemons = Daemons::start(&url, &datadir, &config)?;
+    let daemons = Daemons::start(&url, &datadir, &config)?;
+
+    if let Some(grpc_port) = args.grpc_port {
+        spawn_grpc_server(grpc_port, &config)?;
+    }
+
     let (tx, rx): (Sender<()>, Receiver<()>) = mpsc::channel();
     ctrlc::set_handler(move || {
         tx.send(()).unwrap();
     })
     .expect("Error setting Ctrl-C handler");
-    rx.recv()?;
+
+    if args.no_supervise {
+        rx.recv()?;
+    } else {
+        supervisor::run(&url, &datadir, &config, rx, daemons)?;
+    }
+    Ok(())
+}
+
+/// Build the same RPC clients the CLI builds and serve them over gRPC on a
+/// background thread so embedders/GUIs can drive switchboard without
+/// shelling out to `switchboard-cli`.
+fn spawn_grpc_server(port: u16, config: &Config) -> Result<()> {
+    let main = ureq_jsonrpc::Client {
+        host: "localhost".to_string(),
+        port: config.main.port,
+        user: config.switchboard.rpcuser.clone(),
+        password: config.switchboard.rpcpassword.clone(),
+        id: "switchboardd-grpc".to_string(),
+    };
+    let zcash = ureq_jsonrpc::Client {
+        host: "localhost".to_string(),
+        port: config.zcash.port,
+        user: config.switchboard.rpcuser.clone(),
+        password: config.switchboard.rpcpassword.clone(),
+        id: "switchboardd-grpc".to_string(),
+    };
+    let eth_transport =
+        web3::transports::Http::new(&format!("http://localhost:{}", config.ethereum.port))?;
+    let web3 = web3::Web3::new(eth_transport.clone());
+    let service = grpc::SwitchboardService {
+        config: config.clone(),
+        main,
+        zcash,
+        web3,
+        eth_transport,
+    };
+    let addr = ([0, 0, 0, 0], port).into();
+
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Runtime::new().expect("failed to start gRPC runtime");
+        if let Err(err) = runtime.block_on(grpc::serve(addr, service)) {
+            eprintln!("gRPC server error: {:#}", err);
+        }
+    });
     Ok(())
 }
@@ -0,0 +1,135 @@
+//! Daemon liveness supervision.
+//!
+//! `Daemons` (from `switchboard::launcher`) only exposes `start`, not a
+//! handle per child process, so this supervisor cannot restart a single
+//! crashed daemon in isolation: it probes the whole stack's RPC surface on
+//! an interval, and once a probe has failed `FAILURE_THRESHOLD` times in a
+//! row it drops the current `Daemons` (killing whatever is still running,
+//! healthy or not) and starts a fresh one, backing off exponentially
+//! between attempts up to `MAX_RESTARTS` consecutive restarts. Requiring
+//! more than one consecutive miss before acting keeps a momentarily slow
+//! RPC (e.g. geth under load) from needlessly killing the other two
+//! daemons, though it cannot tell a genuinely wedged daemon from one that
+//! is merely slow to answer. This is also liveness-by-RPC only: a daemon
+//! whose process has exited but whose port is still held by something
+//! else would still "pass". Real per-daemon restart and exit detection
+//! would need `launcher` to expose child handles, which it doesn't today.
+//! RPC clients are rebuilt from `config` on every probe rather than held
+//! open, so a restarted daemon is reconnected to automatically instead of
+//! leaving the supervisor stuck on a dead connection.
+
+use anyhow::{anyhow, Result};
+use futures::executor::block_on;
+use std::path::Path;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::thread::sleep;
+use std::time::Duration;
+use switchboard::{config::Config, launcher::Daemons};
+
+const PROBE_INTERVAL: Duration = Duration::from_secs(30);
+const BASE_BACKOFF: Duration = Duration::from_secs(5);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+const MAX_RESTARTS: u32 = 10;
+/// Consecutive failed probes required before restarting, so one slow
+/// response doesn't kill daemons that are merely momentarily wedged.
+const FAILURE_THRESHOLD: u32 = 2;
+
+fn rpc_clients(config: &Config) -> (ureq_jsonrpc::Client, ureq_jsonrpc::Client) {
+    let main = ureq_jsonrpc::Client {
+        host: "localhost".to_string(),
+        port: config.main.port,
+        user: config.switchboard.rpcuser.clone(),
+        password: config.switchboard.rpcpassword.clone(),
+        id: "switchboardd-supervisor".to_string(),
+    };
+    let zcash = ureq_jsonrpc::Client {
+        host: "localhost".to_string(),
+        port: config.zcash.port,
+        user: config.switchboard.rpcuser.clone(),
+        password: config.switchboard.rpcpassword.clone(),
+        id: "switchboardd-supervisor".to_string(),
+    };
+    (main, zcash)
+}
+
+/// Probe each daemon's RPC: `getblockcount` for the mainchain and zcash
+/// nodes, `eth_blockNumber` (via `block_number`) for geth.
+fn probe(config: &Config) -> bool {
+    let (main, zcash) = rpc_clients(config);
+    let main_ok = main.send_request::<usize>("getblockcount", &[]).is_ok();
+    let zcash_ok = zcash.send_request::<usize>("getblockcount", &[]).is_ok();
+    let eth_ok = web3::transports::Http::new(&format!("http://localhost:{}", config.ethereum.port))
+        .map(web3::Web3::new)
+        .map(|web3| block_on(web3.eth().block_number()).is_ok())
+        .unwrap_or(false);
+    main_ok && zcash_ok && eth_ok
+}
+
+/// Ask the mainchain/zcash daemons to stop over RPC before dropping
+/// `Daemons`, which force-kills anything still running. Best-effort: a
+/// daemon that is already wedged or gone just gets killed by the drop.
+fn shutdown(config: &Config, daemons: Daemons) {
+    let (main, zcash) = rpc_clients(config);
+    let _ = main.send_request::<serde_json::Value>("stop", &[]);
+    let _ = zcash.send_request::<serde_json::Value>("stop", &[]);
+    drop(daemons);
+}
+
+/// Probe liveness on `PROBE_INTERVAL`; once `FAILURE_THRESHOLD` probes in a
+/// row have failed, drop and restart the whole daemon stack with
+/// exponential backoff, logging every transition, up to `MAX_RESTARTS`
+/// consecutive restarts. Returns once `rx` receives the Ctrl-C signal,
+/// after an orderly shutdown.
+pub fn run(
+    url: &str,
+    datadir: &Path,
+    config: &Config,
+    rx: Receiver<()>,
+    mut daemons: Daemons,
+) -> Result<()> {
+    let mut attempt = 0u32;
+    let mut failures = 0u32;
+    loop {
+        match rx.recv_timeout(PROBE_INTERVAL) {
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => {
+                shutdown(config, daemons);
+                return Ok(());
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                if probe(config) {
+                    attempt = 0;
+                    failures = 0;
+                    continue;
+                }
+                failures += 1;
+                if failures < FAILURE_THRESHOLD {
+                    eprintln!(
+                        "daemon stack missed its liveness probe ({}/{}), giving it another cycle",
+                        failures, FAILURE_THRESHOLD
+                    );
+                    continue;
+                }
+                if attempt >= MAX_RESTARTS {
+                    shutdown(config, daemons);
+                    return Err(anyhow!(
+                        "daemon stack still failing its liveness probe after {} restarts, giving up",
+                        MAX_RESTARTS
+                    ));
+                }
+                let backoff = (BASE_BACKOFF * 2u32.pow(attempt)).min(MAX_BACKOFF);
+                eprintln!(
+                    "daemon stack failed its liveness probe {} times in a row, restarting in {:?} (attempt {}/{})",
+                    failures,
+                    backoff,
+                    attempt + 1,
+                    MAX_RESTARTS
+                );
+                drop(daemons);
+                sleep(backoff);
+                daemons = Daemons::start(url, datadir, config)?;
+                attempt += 1;
+                failures = 0;
+            }
+        }
+    }
+}